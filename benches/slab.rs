@@ -0,0 +1,62 @@
+//! Compares [`SlabAllocator`] against [`Global`] for the small, same-sized
+//! buffers a backward pass churns through.
+//!
+//! Run with `cargo bench --bench slab`. Build with
+//! `RUSTFLAGS="-C target-cpu=native"` to measure AVX2 (32-byte) alignment.
+
+#![feature(allocator_api, test)]
+
+extern crate test;
+
+use std::alloc::{Allocator, Global};
+
+use autodiff::{memory::slab::SlabAllocator, storage::Storage};
+use test::{Bencher, black_box};
+
+/// Element counts of the buffers created per iteration.
+const SIZES: [usize; 4] = [1, 16, 64, 256];
+
+/// Number of same-shape gradients alive at once in [`grads`].
+const LIVE_GRADS: usize = 128;
+
+/// Creates and immediately drops one storage of each size.
+fn create_drop<A: Allocator + Clone>(b: &mut Bencher, alloc: &A) {
+    b.iter(|| {
+        for &numel in &SIZES {
+            black_box(Storage::filled_with(numel, 0.0f32, alloc.clone()));
+        }
+    });
+}
+
+/// Keeps `LIVE_GRADS` gradients alive before dropping them all, like the
+/// accumulation buffers of one backward pass.
+fn grads<A: Allocator + Clone>(b: &mut Bencher, alloc: &A) {
+    b.iter(|| {
+        let grads: Vec<_> = (0..LIVE_GRADS)
+            .map(|_| Storage::filled_with(64, 0.0f32, alloc.clone()))
+            .collect();
+        black_box(grads)
+    });
+}
+
+#[bench]
+fn create_drop_global(b: &mut Bencher) {
+    create_drop(b, &Global);
+}
+
+#[bench]
+fn create_drop_slab(b: &mut Bencher) {
+    let slab = SlabAllocator::new();
+    create_drop(b, &&slab);
+}
+
+#[bench]
+fn grads_global(b: &mut Bencher) {
+    grads(b, &Global);
+}
+
+#[bench]
+fn grads_slab(b: &mut Bencher) {
+    let slab = SlabAllocator::new();
+    grads(b, &&slab);
+}
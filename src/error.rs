@@ -1,30 +1,224 @@
+use core::alloc::{AllocError, Layout};
+
+use alloc::{boxed::Box, string::String};
+
+#[cfg(all(feature = "std", debug_assertions))]
+use alloc::sync::Arc;
+#[cfg(all(feature = "std", debug_assertions))]
+use std::backtrace::{Backtrace, BacktraceStatus};
+
 use crate::shape::Shape;
 
 #[derive(Debug, Clone)]
 pub enum TensorError {
-    InconsistentDims { expected: Shape, received: Shape },
-    Memory(String),
-    Broadcast { d1: usize, d2: usize },
+    InconsistentDims {
+        expected: Shape,
+        received: Shape,
+    },
+    /// The allocator could not satisfy `layout`.
+    AllocationFailed {
+        layout: Layout,
+        source: AllocError,
+    },
+    /// `numel` elements of `elem_size` bytes, padded to `align`, exceed the
+    /// maximum allocation size.
+    CapacityOverflow {
+        numel: usize,
+        elem_size: usize,
+        align: usize,
+    },
+    /// `index` does not address an element of `shape`.
+    IndexOutOfBounds {
+        index: Box<[usize]>,
+        shape: Shape,
+    },
+    /// Element types, as reported by [`core::any::type_name`].
+    DtypeMismatch {
+        expected: &'static str,
+        received: &'static str,
+    },
+    Broadcast {
+        d1: usize,
+        d2: usize,
+    },
     InvalidOp(String),
+    ShapeMismatch(OpContext),
+}
+
+/// Operation and operand shapes recorded when an op rejects its inputs.
+///
+/// In debug builds with the `std` feature, the creation site is captured as a
+/// [`std::backtrace::Backtrace`]. Capture is controlled by the usual
+/// `RUST_LIB_BACKTRACE` / `RUST_BACKTRACE` environment variables. Use
+/// [`OpContext::new`] to construct one in every build profile.
+#[derive(Clone)]
+#[non_exhaustive]
+pub struct OpContext {
+    pub op_name: &'static str,
+    pub lhs_shape: Shape,
+    pub rhs_shape: Shape,
+    #[cfg(all(feature = "std", debug_assertions))]
+    backtrace: Arc<Backtrace>,
+}
+
+impl OpContext {
+    pub fn new(op_name: &'static str, lhs: &Shape, rhs: &Shape) -> Self {
+        Self {
+            op_name,
+            lhs_shape: lhs.clone(),
+            rhs_shape: rhs.clone(),
+            #[cfg(all(feature = "std", debug_assertions))]
+            backtrace: Arc::new(Backtrace::capture()),
+        }
+    }
+
+    /// Returns the backtrace captured when this context was created.
+    ///
+    /// Always `None` in release builds, or when capture is disabled.
+    #[cfg(feature = "std")]
+    pub fn backtrace(&self) -> Option<&std::backtrace::Backtrace> {
+        #[cfg(debug_assertions)]
+        {
+            let backtrace: &Backtrace = &self.backtrace;
+            (backtrace.status() == BacktraceStatus::Captured).then_some(backtrace)
+        }
+        #[cfg(not(debug_assertions))]
+        {
+            None
+        }
+    }
+
+    /// Returns a hint on how the operands could be fixed, if one is known.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use autodiff::{error::TensorError, shape};
+    ///
+    /// let err = shape![2, 3].can_broadcast_matmul(&shape![4, 3]).unwrap_err();
+    /// let TensorError::ShapeMismatch(ctx) = &err else { unreachable!() };
+    /// assert_eq!(ctx.suggestion(), Some("did you mean to transpose rhs?"));
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "matmul cannot be applied to lhs: Shape([2, 3]), rhs: Shape([4, 3]) \
+    ///      (did you mean to transpose rhs?)"
+    /// );
+    ///
+    /// // Mismatched batch dimensions keep the op context, without a hint.
+    /// let err = shape![2, 3, 4].can_broadcast_matmul(&shape![3, 4, 5]).unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "matmul cannot be applied to lhs: Shape([2, 3, 4]), rhs: Shape([3, 4, 5])"
+    /// );
+    /// ```
+    pub fn suggestion(&self) -> Option<&'static str> {
+        let (a, b) = (self.lhs_shape.dims(), self.rhs_shape.dims());
+        if self.op_name != "matmul" {
+            return None;
+        }
+
+        if a.is_empty() || b.is_empty() {
+            return Some("both operands need at least 1 dimension");
+        }
+
+        if a.len() < 2 || b.len() < 2 {
+            return None;
+        }
+
+        let (a_m, a_k) = (a[a.len() - 2], a[a.len() - 1]);
+        let (b_k, b_n) = (b[b.len() - 2], b[b.len() - 1]);
+        if a_k == b_k {
+            // The inner dimensions fit; only the batch dimensions disagree.
+            None
+        } else if a_k == b_n {
+            Some("did you mean to transpose rhs?")
+        } else if a_m == b_k {
+            Some("did you mean to transpose lhs?")
+        } else if a_m == b_n {
+            Some("did you mean to swap lhs and rhs?")
+        } else {
+            None
+        }
+    }
+}
+
+impl PartialEq for OpContext {
+    fn eq(&self, other: &Self) -> bool {
+        self.op_name == other.op_name
+            && self.lhs_shape == other.lhs_shape
+            && self.rhs_shape == other.rhs_shape
+    }
+}
+
+impl Eq for OpContext {}
+
+impl core::fmt::Debug for OpContext {
+    /// Shows the operation and shapes, but only the status of the backtrace,
+    /// so that `unwrap` stays readable. The full trace is printed by the
+    /// alternate `Display` form (`{:#}`) of [`TensorError`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use autodiff::{error::TensorError, shape};
+    ///
+    /// let err = shape![2, 3].can_broadcast_matmul(&shape![4, 3]).unwrap_err();
+    /// let TensorError::ShapeMismatch(ctx) = &err else { unreachable!() };
+    /// let debug = format!("{ctx:?}");
+    /// assert!(debug.starts_with(
+    ///     "OpContext { op_name: \"matmul\", lhs_shape: Shape([2, 3]), rhs_shape: Shape([4, 3])"
+    /// ));
+    /// assert_eq!(debug.lines().count(), 1);
+    /// ```
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut s = f.debug_struct("OpContext");
+        s.field("op_name", &self.op_name)
+            .field("lhs_shape", &self.lhs_shape)
+            .field("rhs_shape", &self.rhs_shape);
+        #[cfg(all(feature = "std", debug_assertions))]
+        s.field("backtrace", &self.backtrace.status());
+        s.finish()
+    }
 }
 
 impl TensorError {
+    /// Returns the creation-site backtrace recorded in the error, if any.
+    ///
+    /// See [`OpContext::backtrace`].
+    #[cfg(feature = "std")]
+    pub fn backtrace(&self) -> Option<&std::backtrace::Backtrace> {
+        match self {
+            TensorError::ShapeMismatch(ctx) => ctx.backtrace(),
+            _ => None,
+        }
+    }
+
     pub fn inconsistent(expected: &[usize], received: &[usize]) -> Self {
         Self::InconsistentDims {
             expected: Shape::from(expected),
             received: Shape::from(received),
         }
     }
+
+    pub fn dtype_mismatch<Expected, Received>() -> Self {
+        Self::DtypeMismatch {
+            expected: core::any::type_name::<Expected>(),
+            received: core::any::type_name::<Received>(),
+        }
+    }
 }
 
-impl std::error::Error for TensorError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        None
+impl core::error::Error for TensorError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            TensorError::AllocationFailed { source, .. } => Some(source),
+            _ => None,
+        }
     }
 }
 
-impl std::fmt::Display for TensorError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for TensorError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             TensorError::InconsistentDims { expected, received } => {
                 write!(
@@ -32,8 +226,33 @@ impl std::fmt::Display for TensorError {
                     "inconsistent dimensions. expected: {expected}, received: {received}"
                 )
             }
-            TensorError::Memory(why) => {
-                write!(f, "memory handling violation: {why}")
+            TensorError::AllocationFailed { layout, .. } => {
+                write!(
+                    f,
+                    "failed to allocate {} bytes with alignment {}",
+                    layout.size(),
+                    layout.align()
+                )
+            }
+            TensorError::CapacityOverflow {
+                numel,
+                elem_size,
+                align,
+            } => {
+                write!(
+                    f,
+                    "cannot allocate {numel} elements of {elem_size} bytes with alignment {align}: \
+                     size overflows isize::MAX"
+                )
+            }
+            TensorError::IndexOutOfBounds { index, shape } => {
+                write!(f, "index {index:?} is out of bounds for {shape}")
+            }
+            TensorError::DtypeMismatch { expected, received } => {
+                write!(
+                    f,
+                    "dtype mismatch. expected: {expected}, received: {received}"
+                )
             }
             TensorError::InvalidOp(err) => {
                 write!(f, "invalid operation: {err}")
@@ -41,6 +260,21 @@ impl std::fmt::Display for TensorError {
             TensorError::Broadcast { d1: dim1, d2: dim2 } => {
                 write!(f, "cannot broadcast dimensions: {dim1} vs {dim2}")
             }
+            TensorError::ShapeMismatch(ctx) => {
+                write!(
+                    f,
+                    "{} cannot be applied to lhs: {}, rhs: {}",
+                    ctx.op_name, ctx.lhs_shape, ctx.rhs_shape
+                )?;
+                if let Some(hint) = ctx.suggestion() {
+                    write!(f, " ({hint})")?;
+                }
+                #[cfg(feature = "std")]
+                if let Some(backtrace) = ctx.backtrace().filter(|_| f.alternate()) {
+                    write!(f, "\n{backtrace}")?;
+                }
+                Ok(())
+            }
         }
     }
 }
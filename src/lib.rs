@@ -8,6 +8,8 @@
 //! - Uses 32-byte aligned memory allocation suitable for AVX2/SIMD optimizations.
 //! - Provides a basic `Tensor` type with shape tracking.
 //! - Keeps external dependencies to a minimum.
+//! - The core (`Buffer`, `Storage`, `Shape`) builds under `no_std` with `alloc`
+//!   when the default `std` feature is disabled.
 //!
 //! ## Motivation
 //!
@@ -18,7 +20,8 @@
 //!
 //! **Note:** This is a work-in-progress and primarily for educational purposes. It is **not** production-ready.
 
-#![feature(allocator_api)]
+#![no_std]
+#![feature(allocator_api, strict_provenance_lints)]
 #![warn(
     clippy::perf,
     clippy::correctness,
@@ -28,9 +31,17 @@
     clippy::pedantic
 )]
 #![deny(clippy::undocumented_unsafe_blocks, clippy::cast_possible_truncation)]
+#![deny(fuzzy_provenance_casts, lossy_provenance_casts)]
 #![allow(clippy::float_cmp, clippy::must_use_candidate)]
 
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
 pub mod error;
 pub mod memory;
+pub mod random;
 pub mod shape;
 pub mod storage;
+#[cfg(feature = "testing")]
+pub mod testing;
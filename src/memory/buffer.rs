@@ -1,9 +1,11 @@
-use std::{
+use core::{
     alloc::{Allocator, Layout},
     marker::PhantomData,
+    ops::Range,
     ptr::NonNull,
 };
 
+use crate::error::TensorError;
 use crate::memory::{
     buffer::utils::zero_trailing_bytes,
     policy::{
@@ -18,11 +20,11 @@ use crate::memory::{
 /// Ensures specific memory alignment with AVX and NEON requirements.
 /// defaulting to 32 bytes for `x86_64` when AVX2 is enabled.
 /// and 16 bytes for `aarch64` when NEON is enabled.
-/// Defaults to `std::mem::size_of::<T>()` otherwise.
+/// Defaults to `core::mem::size_of::<T>()` otherwise.
 ///
 /// # Note
 ///
-/// `RawStorage` only drops the underlying allocation. 
+/// `Buffer` only drops the underlying allocation.
 /// It will **NOT** drop the `T` present in the allocated memory.
 /// This storage is intended to be a low-surface-area unsafe pool 
 /// of aligned memory that can later be layered on with a safe abstraction
@@ -79,14 +81,48 @@ impl<I: InitStrategy, A: AlignmentStrategy> BufferBuilder<I, A> {
         }
     }
 
+    /// # Panics
+    ///
+    /// Panics if the allocation fails. See [`BufferBuilder::try_build`].
     #[must_use]
     pub fn build<T, Alloc: Allocator + Clone>(self, alloc: Alloc) -> Buffer<T, Alloc> {
         Buffer::with_alignment::<I, A>(self.numel, alloc)
     }
+
+    /// Builds the buffer, returning an error instead of panicking if the allocator fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TensorError::CapacityOverflow`] if the allocation size
+    /// overflows, or [`TensorError::AllocationFailed`] if the allocator fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is a zero-sized type or `numel` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use std::alloc::Global;
+    ///
+    /// use autodiff::{error::TensorError, memory::BufferBuilder};
+    ///
+    /// let err = BufferBuilder::new(usize::MAX - 2)
+    ///     .try_build::<u8, _>(Global)
+    ///     .unwrap_err();
+    /// assert!(matches!(err, TensorError::CapacityOverflow { .. }));
+    /// ```
+    pub fn try_build<T, Alloc: Allocator + Clone>(
+        self,
+        alloc: Alloc,
+    ) -> Result<Buffer<T, Alloc>, TensorError> {
+        Buffer::try_with_alignment::<I, A>(self.numel, alloc)
+    }
 }
 
 impl<T, A: Allocator + Clone> Buffer<T, A> {
-    /// Returns a `RawStorage` with specified attributes.
+    /// Returns a `Buffer` with specified attributes.
     ///
     /// # Arguments
     ///
@@ -95,46 +131,148 @@ impl<T, A: Allocator + Clone> Buffer<T, A> {
     ///
     /// # Panics
     ///
-    /// Panics if `T` is a Zero-Sized Type, `numel` is 0, or `align` is not a power of two.
+    /// Panics if `T` is a Zero-Sized Type, `numel` is 0, `align` is not a power of two,
+    /// the allocation size overflows, or the allocator fails.
     fn with_alignment<I: InitStrategy, Align: AlignmentStrategy>(
         numel: usize,
         allocator: A,
     ) -> Self {
-        assert!((std::mem::size_of::<T>() != 0), "ZSTs are not supported.");
+        Self::try_with_alignment::<I, Align>(numel, allocator)
+            .unwrap_or_else(|err| panic!("allocator failed to allocate valid layout: {err}"))
+    }
+
+    /// Fallible version of [`Buffer::with_alignment`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TensorError::CapacityOverflow`] if the allocation size
+    /// overflows, or [`TensorError::AllocationFailed`] if the allocator fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is a Zero-Sized Type, `numel` is 0, or `align` is not a power of two.
+    fn try_with_alignment<I: InitStrategy, Align: AlignmentStrategy>(
+        numel: usize,
+        allocator: A,
+    ) -> Result<Self, TensorError> {
+        assert!((core::mem::size_of::<T>() != 0), "ZSTs are not supported.");
         assert!(
             (numel != 0),
             "zero-sized buffers (numel=0) are not supported."
         );
 
         let align = Align::alignment::<T>();
-        let size = self::utils::align_to::<T>(numel, align);
-        let layout = Layout::from_size_align(size, align).unwrap_or_else(|_| {
-            panic!("layout creation should have valid alignment: {align} and length: {numel}")
-        });
-
-        let ptr = I::allocate(allocator.clone(), layout)
-            .unwrap_or_else(|_| panic!("allocator failed to allocate valid layout: {layout:#?}"));
+        let overflow = || TensorError::CapacityOverflow {
+            numel,
+            elem_size: core::mem::size_of::<T>(),
+            align,
+        };
+        let size = self::utils::align_to::<T>(numel, align).ok_or_else(overflow)?;
+        let layout = Layout::from_size_align(size, align).map_err(|_| overflow())?;
 
-        #[cfg(debug_assertions)]
-        // SAFETY:
-        // - this code is only ran in debug builds.
-        // - `ptr.as_ptr()` is a valid non-null aligned pointer to allocated memory.
-        // - `size` is the number of *bytes* in the array.
-        unsafe {
-            // poison buffer
-            std::ptr::write_bytes(ptr.as_ptr().cast::<u8>(), 0xAB, size);
-        }
+        let ptr = I::allocate(&allocator, layout)
+            .map_err(|source| TensorError::AllocationFailed { layout, source })?;
 
-        zero_trailing_bytes::<T>(ptr.as_ptr().cast::<u8>(), numel, size);
+        zero_trailing_bytes::<T>(ptr.cast::<u8>(), numel, size);
 
-        Buffer {
+        Ok(Buffer {
             ptr: ptr.cast(),
             layout,
             numel,
             allocator,
+        })
+    }
+
+    /// Creates a `Buffer` that takes ownership of an existing allocation.
+    ///
+    /// The padding bytes `[numel * size_of::<T>(), layout.size())` are
+    /// zeroed, as they are for buffers from [`BufferBuilder`], so SIMD loads
+    /// past the last element read zeroes.
+    ///
+    /// # Safety
+    ///
+    /// - `ptr` must have been allocated by `allocator` with exactly `layout`,
+    ///   and must not be freed by anyone else afterwards.
+    /// - `layout.align()` must be at least `align_of::<T>()`.
+    /// - `layout.size()` must be at least `numel * size_of::<T>()`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is a Zero-Sized Type or `numel` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use std::alloc::{Allocator, Global, Layout};
+    ///
+    /// use autodiff::memory::Buffer;
+    ///
+    /// // Three `u32`s in a 16-byte block, with every byte set.
+    /// let layout = Layout::from_size_align(16, 16).unwrap();
+    /// let ptr = Global.allocate(layout).unwrap().cast::<u8>();
+    /// // SAFETY: `ptr` is valid for writes of `layout.size()` bytes.
+    /// unsafe { ptr.write_bytes(0xFF, layout.size()) };
+    ///
+    /// // SAFETY: `ptr` was allocated by `Global` with `layout`, which fits
+    /// // three `u32`s at their alignment.
+    /// let buf = unsafe { Buffer::<u32, _>::from_raw_parts(ptr.cast(), 3, layout, Global) };
+    /// // SAFETY: the padding bytes `[12, 16)` were zeroed on adoption.
+    /// let padding = unsafe { core::slice::from_raw_parts(ptr.as_ptr().add(12), 4) };
+    /// assert_eq!(padding, [0; 4]);
+    ///
+    /// let (ptr, numel, layout, alloc) = buf.into_raw_parts();
+    /// assert_eq!((numel, layout.size()), (3, 16));
+    /// // SAFETY: the parts come straight from `into_raw_parts`.
+    /// let buf = unsafe { Buffer::<u32, _>::from_raw_parts(ptr, numel, layout, alloc) };
+    /// assert_eq!(buf.numel(), 3);
+    /// ```
+    pub unsafe fn from_raw_parts(
+        ptr: NonNull<T>,
+        numel: usize,
+        layout: Layout,
+        allocator: A,
+    ) -> Self {
+        assert!((core::mem::size_of::<T>() != 0), "ZSTs are not supported.");
+        assert!(
+            (numel != 0),
+            "zero-sized buffers (numel=0) are not supported."
+        );
+        debug_assert!(layout.align() >= core::mem::align_of::<T>());
+        debug_assert!(ptr.addr().get().is_multiple_of(layout.align()));
+        debug_assert!(layout.size() >= numel * core::mem::size_of::<T>());
+
+        zero_trailing_bytes::<T>(ptr.cast::<u8>(), numel, layout.size());
+
+        Buffer {
+            ptr,
+            numel,
+            layout,
+            allocator,
         }
     }
 
+    /// Decomposes the buffer into its raw parts without deallocating.
+    ///
+    /// The caller becomes responsible for the allocation, e.g. by passing the
+    /// parts back to [`Buffer::from_raw_parts`].
+    pub fn into_raw_parts(self) -> (NonNull<T>, usize, Layout, A) {
+        let this = core::mem::ManuallyDrop::new(self);
+        // SAFETY:
+        // - `this` is never dropped, so the allocator is moved out exactly once.
+        let allocator = unsafe { core::ptr::read(&raw const this.allocator) };
+        (this.ptr, this.numel, this.layout, allocator)
+    }
+
+    /// Returns the internal pointer to the underlying memory as a [`NonNull`].
+    ///
+    /// Preferred for pointer arithmetic inside the crate, since it keeps
+    /// provenance and non-nullness without round-tripping through raw pointers.
+    #[inline]
+    pub fn as_non_null(&self) -> NonNull<T> {
+        self.ptr
+    }
+
     /// Returns the internal pointer to the underlying memory.
     #[inline]
     pub fn as_ptr(&self) -> *const T {
@@ -147,7 +285,7 @@ impl<T, A: Allocator + Clone> Buffer<T, A> {
         self.ptr.as_ptr()
     }
 
-    /// Return the underlying layout: [`std::alloc::Layout`]
+    /// Return the underlying layout: [`core::alloc::Layout`]
     #[inline]
     pub fn layout(&self) -> Layout {
         self.layout
@@ -164,7 +302,7 @@ impl<T, A: Allocator + Clone> Buffer<T, A> {
     /// This is the total capacity in terms of number of `T` elements.
     #[inline]
     pub fn allocated_capacity(&self) -> usize {
-        self.layout().size() / std::mem::size_of::<T>()
+        self.layout().size() / core::mem::size_of::<T>()
     }
 
     /// Returns the number of elements originally requested (logical length).
@@ -184,7 +322,7 @@ impl<T, A: Allocator + Clone> Buffer<T, A> {
         // SAFETY:
         // - `self.as_ptr()` returns a valid, non-null, aligned pointer.
         // - `self.allocated_capacity()` returns the correct number of elements
-        unsafe { std::slice::from_raw_parts(self.as_ptr(), self.numel()) }
+        unsafe { core::slice::from_raw_parts(self.as_ptr(), self.numel()) }
     }
 
     /// Returns a mutable slice over the logical allocated region.
@@ -198,7 +336,92 @@ impl<T, A: Allocator + Clone> Buffer<T, A> {
         // SAFETY:
         // - `as_mut_ptr` is a pointer to a valid, non-null, aligned pointer.
         // - `self.allocated_capacity()` returns the correct number of elements
-        unsafe { std::slice::from_raw_parts_mut(self.as_mut_ptr(), self.numel()) }
+        unsafe { core::slice::from_raw_parts_mut(self.as_mut_ptr(), self.numel()) }
+    }
+
+    /// Panics if any element in `range` still holds the debug poison pattern.
+    ///
+    /// In debug builds, [`Uninitialized`] buffers are filled with
+    /// [`DEBUG_POISON`](crate::memory::policy::DEBUG_POISON); an element whose bytes all still equal it was most
+    /// likely never written. This is a heuristic: a legitimately written value
+    /// with that bit pattern (e.g. `0xABu8`) is reported as well.
+    ///
+    /// Does nothing in release builds, or under Miri, where memory is not
+    /// poisoned and uninitialized reads are reported by Miri itself.
+    ///
+    /// # Safety
+    ///
+    /// Every byte of the elements in `range` must be initialized. This holds
+    /// in debug builds for any buffer built through [`BufferBuilder`], whose
+    /// memory is either poisoned or zeroed, but not necessarily for buffers
+    /// created with [`Buffer::from_raw_parts`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is not within `[0, numel())`, or if a poisoned
+    /// element is found.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// # #![feature(allocator_api)]
+    /// use std::alloc::Global;
+    ///
+    /// use autodiff::memory::{BufferBuilder, policy::POISON_ENABLED};
+    ///
+    /// let mut buf = BufferBuilder::new(4).build::<u32, _>(Global);
+    /// // SAFETY: element 0 is in bounds and written before it is scanned.
+    /// unsafe {
+    ///     buf.as_mut_ptr().write(7);
+    ///     buf.debug_assert_initialized(0..1);
+    /// }
+    ///
+    /// // Without poisoning the rest of the buffer is truly uninitialized,
+    /// // and scanning it would be undefined behavior.
+    /// if !POISON_ENABLED {
+    ///     panic!("the scan is disabled in this build");
+    /// }
+    /// // SAFETY: elements `[1, 4)` are in bounds and still poisoned.
+    /// unsafe { buf.debug_assert_initialized(1..4) };
+    /// ```
+    #[track_caller]
+    pub unsafe fn debug_assert_initialized(&self, range: Range<usize>) {
+        #[cfg(all(debug_assertions, not(miri)))]
+        {
+            use crate::memory::policy::DEBUG_POISON;
+
+            assert!(
+                range.start <= range.end && range.end <= self.numel,
+                "range {range:?} out of bounds for buffer of {} elements",
+                self.numel
+            );
+
+            let size = core::mem::size_of::<T>();
+            // SAFETY:
+            // - `range` is within the allocated elements, checked above.
+            // - the caller guarantees every byte in `range` is initialized.
+            let bytes = unsafe {
+                NonNull::slice_from_raw_parts(
+                    self.ptr.cast::<u8>().add(range.start * size),
+                    range.len() * size,
+                )
+                .as_ref()
+            };
+
+            if let Some(i) = bytes
+                .chunks_exact(size)
+                .position(|element| element.iter().all(|&b| b == DEBUG_POISON))
+            {
+                let index = range.start + i;
+                panic!(
+                    "element {index} (byte offset {}) still holds debug poison {DEBUG_POISON:#04X}; \
+                     it was likely never initialized",
+                    index * size
+                );
+            }
+        }
+        #[cfg(any(not(debug_assertions), miri))]
+        let _ = range;
     }
 }
 
@@ -215,17 +438,16 @@ impl<T, A: Allocator + Clone> Drop for Buffer<T, A> {
 }
 
 mod utils {
+    use core::ptr::NonNull;
+
     /// Returns allocation size (in bytes) for `numel` elements of `T`,
     /// rounded up to the nearest multiple of `align`.
+    ///
+    /// Returns `None` if the size overflows `usize`.
     #[inline]
-    pub fn align_to<T>(numel: usize, align: usize) -> usize {
-        let tsize = std::mem::size_of::<T>();
-
-        let size_in_bytes = numel
-            .checked_mul(tsize)
-            .unwrap_or_else(|| panic!("numel {numel} * tsize {tsize} overflowed."));
-
-        (size_in_bytes + align - 1) & !(align - 1)
+    pub fn align_to<T>(numel: usize, align: usize) -> Option<usize> {
+        let size_in_bytes = numel.checked_mul(core::mem::size_of::<T>())?;
+        Some(size_in_bytes.checked_add(align - 1)? & !(align - 1))
     }
 
     /// Fills trailing padding bytes with zeroes (if any).
@@ -233,8 +455,8 @@ mod utils {
     /// This is useful when SIMD loads might read past initialized data.
     /// Does nothing if `length * size_of::<T>() >= size`.
     #[inline]
-    pub fn zero_trailing_bytes<T>(ptr: *mut u8, length: usize, size: usize) {
-        let start_offset = length * std::mem::size_of::<T>();
+    pub fn zero_trailing_bytes<T>(ptr: NonNull<u8>, length: usize, size: usize) {
+        let start_offset = length * core::mem::size_of::<T>();
         if start_offset >= size {
             return;
         }
@@ -242,9 +464,9 @@ mod utils {
         let pad_bytes = size - start_offset;
 
         // SAFETY:
-        // - `base.add(start_offset)` is within allocation of `size` bytes
+        // - `ptr.add(start_offset)` is within allocation of `size` bytes
         unsafe {
-            std::ptr::write_bytes(ptr.add(start_offset), 0, pad_bytes);
+            ptr.add(start_offset).write_bytes(0, pad_bytes);
         }
     }
 }
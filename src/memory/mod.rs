@@ -1,2 +1,14 @@
+//! Aligned memory allocation.
+//!
+//! [`Buffer`] is the single raw allocation type used throughout the crate;
+//! its alignment and initialization are selected through the strategies in
+//! [`policy`].
+
 pub mod buffer;
-pub mod policy;
\ No newline at end of file
+pub mod policy;
+#[cfg(feature = "std")]
+pub mod slab;
+#[cfg(feature = "testing")]
+pub mod testing;
+
+pub use buffer::{Buffer, BufferBuilder};
@@ -2,7 +2,7 @@
 //!
 //! This module provides strategy traits that control how memory is allocated and aligned.
 
-use std::{
+use core::{
     alloc::{AllocError, Allocator, Layout},
     ptr::NonNull,
 };
@@ -74,7 +74,7 @@ impl AlignmentStrategy for SimdAlignment {
         )) {
             AVX2_ALIGN
         } else {
-            std::mem::align_of::<T>()
+            core::mem::align_of::<T>()
         };
         assert!(ret.is_power_of_two());
         ret
@@ -110,11 +110,25 @@ impl<const ALIGN: usize> AlignmentStrategy for CustomAlignment<ALIGN> {
     }
 }
 
+/// Byte pattern written over [`Uninitialized`] allocations in debug builds.
+pub const DEBUG_POISON: u8 = 0xAB;
+
+/// Whether this build of the crate poisons [`Uninitialized`] allocations with
+/// [`DEBUG_POISON`].
+///
+/// `true` in debug builds outside of Miri. Reflects how the crate itself was
+/// compiled, which may differ from the `debug_assertions` of a dependent crate.
+pub const POISON_ENABLED: bool = cfg!(all(debug_assertions, not(miri)));
+
 /// Uninitialized memory allocation strategy.
 ///
 /// Allocates memory without initializing it, leaving the contents undefined.
 /// This is the fastest allocation strategy. 
 ///
+/// In debug builds the memory is poisoned with [`DEBUG_POISON`] so that reads
+/// of uninitialized elements are easier to spot. Poisoning is skipped under
+/// Miri, which tracks uninitialized memory precisely on its own.
+///
 /// # Safety
 /// 
 /// Memory allocated with this strategy contains undefined values. Users must
@@ -122,7 +136,17 @@ impl<const ALIGN: usize> AlignmentStrategy for CustomAlignment<ALIGN> {
 pub struct Uninitialized;
 impl InitStrategy for Uninitialized {
     fn allocate<A: Allocator>(allocator: A, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
-        allocator.allocate(layout)
+        let ptr = allocator.allocate(layout)?;
+
+        #[cfg(all(debug_assertions, not(miri)))]
+        // SAFETY:
+        // - this code is only ran in debug builds.
+        // - `ptr` is a valid non-null pointer to `ptr.len()` allocated bytes.
+        unsafe {
+            ptr.cast::<u8>().write_bytes(DEBUG_POISON, ptr.len());
+        }
+
+        Ok(ptr)
     }
 }
 
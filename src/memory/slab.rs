@@ -0,0 +1,361 @@
+//! Size-class slab allocator for many small, same-sized allocations.
+//!
+//! Training graphs allocate and free thousands of small gradient buffers of
+//! identical sizes. [`SlabAllocator`] serves those from per-size-class free
+//! lists carved out of large slabs, so steady-state allocation never reaches
+//! the underlying allocator.
+//!
+//! Each thread keeps a small cache of free blocks in front of the shared,
+//! per-class locked free lists, so the common allocate/free pair takes no
+//! lock at all. See `benches/slab.rs` for a comparison against `Global`.
+//!
+//! Only available with the `std` feature.
+
+use core::{
+    alloc::{AllocError, Allocator, Layout},
+    cell::RefCell,
+    ptr::NonNull,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use alloc::{
+    alloc::Global,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+
+/// Smallest size class, as a power of two (16 bytes).
+const MIN_CLASS_SHIFT: u32 = 4;
+/// Largest size class, as a power of two (64 KiB).
+const MAX_CLASS_SHIFT: u32 = 16;
+const NUM_CLASSES: usize = (MAX_CLASS_SHIFT - MIN_CLASS_SHIFT + 1) as usize;
+
+/// Default number of bytes requested from the inner allocator per slab.
+pub const DEFAULT_SLAB_BYTES: usize = 256 * 1024;
+
+/// Bytes of free blocks each thread may cache per size class.
+const THREAD_CACHE_BYTES: usize = 64 * 1024;
+
+/// Source of unique allocator ids. `0` marks an unowned thread cache.
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Allocator serving small requests from power-of-two size-class slabs.
+///
+/// - Requests up to 64 KiB are rounded up to the next power-of-two size class
+///   (at least 16 bytes, and at least the requested alignment). Blocks of a
+///   class are naturally aligned to their size.
+/// - When a class has no free block, a new slab of [`DEFAULT_SLAB_BYTES`]
+///   (or one block, if larger) is taken from the inner allocator and split.
+/// - Freed blocks go back to the freeing thread's cache, and from there to
+///   their class's free list in batches. Slabs are only returned to the inner
+///   allocator once the last clone is dropped.
+/// - Larger requests are forwarded to the inner allocator untouched.
+///
+/// Clones share the same slabs and free lists. Cloning touches a reference
+/// count; on hot paths, passing `&SlabAllocator` (itself an [`Allocator`])
+/// avoids that.
+///
+/// A thread caches blocks for one slab allocator at a time. Alternating
+/// between several slab allocators on one thread still works, but flushes
+/// that cache on every switch.
+///
+/// # Examples
+///
+/// ```
+/// use autodiff::{memory::slab::SlabAllocator, storage::Storage};
+///
+/// let slab = SlabAllocator::new();
+/// for _ in 0..1000 {
+///     let grad = Storage::filled_with(64, 0.0f32, slab.clone());
+///     drop(grad);
+/// }
+/// assert_eq!(slab.slab_count(), 1);
+/// ```
+pub struct SlabAllocator<A: Allocator = Global> {
+    shared: Arc<Shared<A>>,
+}
+
+struct Shared<A: Allocator> {
+    /// Unique across all slab allocators, so a thread cache never mistakes a
+    /// new allocator for a dropped one.
+    id: u64,
+    inner: A,
+    slab_bytes: usize,
+    classes: [Mutex<SizeClass>; NUM_CLASSES],
+}
+
+#[derive(Default)]
+struct SizeClass {
+    /// Every slab taken from the inner allocator, with its layout.
+    slabs: Vec<(NonNull<u8>, Layout)>,
+    /// Blocks available for reuse.
+    free: Vec<NonNull<u8>>,
+}
+
+// SAFETY:
+// - the pointers in `SizeClass` are owned by the allocator and only accessed
+//   while holding the mutex.
+unsafe impl Send for SizeClass {}
+
+/// Blocks cached by one thread for the slab allocator `owner`.
+struct ThreadCache {
+    owner: u64,
+    /// Where the cached blocks go back to when the owner changes or the
+    /// thread exits. Dead if the owner was dropped, in which case the blocks
+    /// were freed with its slabs.
+    pool: Option<Weak<dyn Pool>>,
+    bins: [Vec<NonNull<u8>>; NUM_CLASSES],
+}
+
+std::thread_local! {
+    static THREAD_CACHE: RefCell<ThreadCache> = const {
+        RefCell::new(ThreadCache {
+            owner: 0,
+            pool: None,
+            bins: [const { Vec::new() }; NUM_CLASSES],
+        })
+    };
+}
+
+/// Type-erased access to a [`Shared`] free list.
+trait Pool {
+    /// Moves all of `blocks` to the free list of class `index`.
+    fn give_back(&self, index: usize, blocks: &mut Vec<NonNull<u8>>);
+}
+
+impl<A: Allocator> Pool for Shared<A> {
+    fn give_back(&self, index: usize, blocks: &mut Vec<NonNull<u8>>) {
+        self.class(index).free.append(blocks);
+    }
+}
+
+impl ThreadCache {
+    /// Returns every cached block to its owner, if still alive.
+    fn flush(&mut self) {
+        let pool = self.pool.take().and_then(|pool| pool.upgrade());
+        for (index, bin) in self.bins.iter_mut().enumerate() {
+            match &pool {
+                Some(pool) => pool.give_back(index, bin),
+                None => bin.clear(),
+            }
+        }
+        self.owner = 0;
+    }
+}
+
+impl Drop for ThreadCache {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl SlabAllocator {
+    /// Creates a slab allocator backed by [`Global`].
+    pub fn new() -> Self {
+        Self::with_allocator(Global)
+    }
+}
+
+impl Default for SlabAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Allocator> SlabAllocator<A> {
+    /// Creates a slab allocator backed by `inner`, requesting
+    /// [`DEFAULT_SLAB_BYTES`] per slab.
+    pub fn with_allocator(inner: A) -> Self {
+        Self::with_slab_bytes(DEFAULT_SLAB_BYTES, inner)
+    }
+
+    /// Creates a slab allocator backed by `inner`, requesting `slab_bytes` per
+    /// slab. Each slab still holds at least one block of its class.
+    pub fn with_slab_bytes(slab_bytes: usize, inner: A) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+                inner,
+                slab_bytes,
+                classes: Default::default(),
+            }),
+        }
+    }
+
+    /// Returns the number of slabs taken from the inner allocator.
+    pub fn slab_count(&self) -> usize {
+        self.shared.classes().map(|c| c.slabs.len()).sum()
+    }
+
+    /// Returns the total number of bytes held in slabs, in use or free.
+    pub fn reserved_bytes(&self) -> usize {
+        self.shared
+            .classes()
+            .map(|c| c.slabs.iter().map(|(_, layout)| layout.size()).sum::<usize>())
+            .sum()
+    }
+
+    /// Returns the number of blocks on the shared free lists.
+    ///
+    /// Blocks held in per-thread caches are not counted.
+    pub fn free_blocks(&self) -> usize {
+        self.shared.classes().map(|c| c.free.len()).sum()
+    }
+}
+
+impl<A: Allocator> Clone for SlabAllocator<A> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<A: Allocator> core::fmt::Debug for SlabAllocator<A> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SlabAllocator")
+            .field("slab_bytes", &self.shared.slab_bytes)
+            .field("slabs", &self.slab_count())
+            .field("reserved_bytes", &self.reserved_bytes())
+            .field("free_blocks", &self.free_blocks())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A: Allocator> Shared<A> {
+    fn class(&self, index: usize) -> MutexGuard<'_, SizeClass> {
+        self.classes[index]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn classes(&self) -> impl Iterator<Item = MutexGuard<'_, SizeClass>> {
+        (0..NUM_CLASSES).map(|index| self.class(index))
+    }
+}
+
+impl<A: Allocator> Drop for Shared<A> {
+    fn drop(&mut self) {
+        let slabs = self
+            .classes
+            .iter_mut()
+            .flat_map(|c| c.get_mut().unwrap_or_else(PoisonError::into_inner).slabs.drain(..));
+        for (ptr, layout) in slabs {
+            // SAFETY:
+            // - every slab was allocated by `inner` with exactly `layout`.
+            // - no clone is left, so no block of the slab is still in use
+            //   through this allocator. Blocks left in thread caches are
+            //   discarded, never dereferenced, once the owner is gone.
+            unsafe { self.inner.deallocate(ptr, layout) };
+        }
+    }
+}
+
+impl<A: Allocator + 'static> SlabAllocator<A> {
+    /// Runs `f` on this thread's cache, taking it over from another owner if
+    /// needed.
+    ///
+    /// Returns `None` if the cache is unavailable, i.e. already borrowed
+    /// further up the stack or destroyed during thread exit.
+    fn with_cache<R>(&self, f: impl FnOnce(&mut ThreadCache) -> R) -> Option<R> {
+        THREAD_CACHE
+            .try_with(|cache| {
+                let mut cache = cache.try_borrow_mut().ok()?;
+                if cache.owner != self.shared.id {
+                    cache.flush();
+                    cache.owner = self.shared.id;
+                    let pool: Weak<dyn Pool> = Arc::downgrade(&self.shared) as Weak<dyn Pool>;
+                    cache.pool = Some(pool);
+                }
+                Some(f(&mut cache))
+            })
+            .ok()
+            .flatten()
+    }
+
+    /// Takes a block of class `index` from the shared free list, carving a
+    /// new slab if it is empty, and moves a batch of further blocks into this
+    /// thread's cache.
+    fn refill(&self, index: usize, block: usize) -> Result<NonNull<u8>, AllocError> {
+        let mut class = self.shared.class(index);
+
+        if class.free.is_empty() {
+            let slab_bytes = self.shared.slab_bytes.max(block) / block * block;
+            let slab_layout = Layout::from_size_align(slab_bytes, block).map_err(|_| AllocError)?;
+            let slab = self.shared.inner.allocate(slab_layout)?.cast::<u8>();
+            class.slabs.push((slab, slab_layout));
+            // Push in reverse so blocks are handed out in address order.
+            class.free.extend(
+                (0..slab_bytes / block)
+                    .rev()
+                    // SAFETY: `i * block < slab_bytes`, within the slab.
+                    .map(|i| unsafe { slab.add(i * block) }),
+            );
+        }
+
+        let ptr = class.free.pop().ok_or(AllocError)?;
+        let batch = (cache_capacity(block) / 2).min(class.free.len());
+        let from = class.free.len() - batch;
+        self.with_cache(|cache| cache.bins[index].extend(class.free.drain(from..)));
+        Ok(ptr)
+    }
+}
+
+/// Returns `(class index, block size)` serving `layout`, or `None` if the
+/// request is too large for the slabs.
+fn size_class(layout: Layout) -> Option<(usize, usize)> {
+    let block = layout
+        .size()
+        .max(layout.align())
+        .max(1 << MIN_CLASS_SHIFT)
+        .checked_next_power_of_two()?;
+    (block <= 1 << MAX_CLASS_SHIFT)
+        .then(|| ((block.trailing_zeros() - MIN_CLASS_SHIFT) as usize, block))
+}
+
+/// Returns how many blocks of `block` bytes a thread may cache per class.
+fn cache_capacity(block: usize) -> usize {
+    (THREAD_CACHE_BYTES / block).max(2)
+}
+
+// SAFETY:
+// - blocks are disjoint sub-ranges of slabs owned by the allocator, aligned
+//   to their block size, and handed out only while on no free list or
+//   thread cache.
+// - slabs stay allocated until the last clone is dropped, so memory stays
+//   valid for as long as any clone can still be used to deallocate it.
+unsafe impl<A: Allocator + 'static> Allocator for SlabAllocator<A> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let Some((index, block)) = size_class(layout) else {
+            return self.shared.inner.allocate(layout);
+        };
+
+        let ptr = match self.with_cache(|cache| cache.bins[index].pop()) {
+            Some(Some(ptr)) => ptr,
+            _ => self.refill(index, block)?,
+        };
+        Ok(NonNull::slice_from_raw_parts(ptr, block))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        let Some((index, block)) = size_class(layout) else {
+            // SAFETY: requests outside the size classes were allocated by `inner`.
+            return unsafe { self.shared.inner.deallocate(ptr, layout) };
+        };
+
+        let cached = self.with_cache(|cache| {
+            let bin = &mut cache.bins[index];
+            bin.push(ptr);
+            let capacity = cache_capacity(block);
+            if bin.len() > capacity {
+                // Keep the most recently freed (cache-hot) half.
+                let excess = bin.len() - capacity / 2;
+                self.shared.class(index).free.extend(bin.drain(..excess));
+            }
+        });
+        if cached.is_none() {
+            self.shared.class(index).free.push(ptr);
+        }
+    }
+}
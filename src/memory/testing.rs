@@ -0,0 +1,253 @@
+//! Allocators for exercising memory handling in tests.
+//!
+//! Only available with the `testing` feature.
+//!
+//! [`Buffer`](crate::memory::Buffer) clones its allocator, so every allocator
+//! here keeps its state behind a shared [`Arc`]: all clones observe and
+//! update the same counters.
+
+use core::{
+    alloc::{AllocError, Allocator, Layout},
+    ptr::NonNull,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use alloc::{alloc::Global, sync::Arc, vec::Vec};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Allocator that fails exactly the `n`-th allocation attempt.
+///
+/// Every other request is forwarded to the inner allocator, so fallible
+/// constructors such as [`Storage::try_new`](crate::storage::Storage::try_new)
+/// can be driven into their error paths deterministically.
+///
+/// # Examples
+///
+/// ```
+/// use autodiff::{memory::testing::FailingAllocator, storage::Storage};
+///
+/// let alloc = FailingAllocator::fail_on(2);
+/// assert!(Storage::<f32, _>::try_new(4, alloc.clone()).is_ok());
+/// assert!(Storage::<f32, _>::try_new(4, alloc.clone()).is_err());
+/// assert_eq!(alloc.attempts(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FailingAllocator<A: Allocator = Global> {
+    inner: A,
+    state: Arc<FailState>,
+}
+
+#[derive(Debug)]
+struct FailState {
+    /// 1-based index of the attempt to fail; 0 never fails.
+    fail_on: usize,
+    attempts: AtomicUsize,
+}
+
+impl FailingAllocator {
+    /// Fails the `n`-th (1-based) allocation made through [`Global`].
+    ///
+    /// `n == 0` never fails.
+    pub fn fail_on(n: usize) -> Self {
+        Self::with_allocator(n, Global)
+    }
+}
+
+impl<A: Allocator> FailingAllocator<A> {
+    /// Fails the `n`-th (1-based) allocation made through `inner`.
+    ///
+    /// `n == 0` never fails.
+    pub fn with_allocator(n: usize, inner: A) -> Self {
+        Self {
+            inner,
+            state: Arc::new(FailState {
+                fail_on: n,
+                attempts: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Returns the number of allocation attempts made so far, failed ones included.
+    pub fn attempts(&self) -> usize {
+        self.state.attempts.load(Ordering::Relaxed)
+    }
+
+    fn check(&self) -> Result<(), AllocError> {
+        let attempt = self.state.attempts.fetch_add(1, Ordering::Relaxed) + 1;
+        if attempt == self.state.fail_on {
+            Err(AllocError)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// SAFETY:
+// - all memory is allocated and freed by `inner`; this wrapper only decides
+//   whether a request reaches it.
+unsafe impl<A: Allocator> Allocator for FailingAllocator<A> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.check()?;
+        self.inner.allocate(layout)
+    }
+
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.check()?;
+        self.inner.allocate_zeroed(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        // SAFETY: upheld by the caller; `ptr` was allocated by `inner`.
+        unsafe { self.inner.deallocate(ptr, layout) }
+    }
+}
+
+/// Allocator that tracks every live allocation and its [`Layout`].
+///
+/// - Deallocating an unknown pointer, or with a layout that differs from the
+///   one used to allocate it, panics immediately.
+/// - When the last clone is dropped, any allocation still outstanding is
+///   reported as a leak by panicking (unless the thread is already panicking).
+///
+/// A leaked [`Buffer`](crate::memory::Buffer) keeps its own clone of the
+/// allocator alive, so the drop-time check cannot fire for it; call
+/// [`LeakCheckAllocator::assert_no_leaks`] at the end of a test instead.
+///
+/// # Examples
+///
+/// ```
+/// use autodiff::{memory::testing::LeakCheckAllocator, storage::Storage};
+///
+/// let alloc = LeakCheckAllocator::new();
+/// let storage = Storage::<f32, _>::new(16, alloc.clone());
+/// assert_eq!(alloc.outstanding(), 1);
+/// drop(storage);
+/// alloc.assert_no_leaks();
+/// ```
+#[derive(Debug, Clone)]
+pub struct LeakCheckAllocator<A: Allocator = Global> {
+    inner: A,
+    state: Arc<LeakState>,
+}
+
+#[derive(Debug, Default)]
+struct LeakState {
+    /// Address and layout of every live allocation.
+    live: Mutex<Vec<(usize, Layout)>>,
+}
+
+impl LeakState {
+    fn live(&self) -> MutexGuard<'_, Vec<(usize, Layout)>> {
+        self.live.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Drop for LeakState {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            return;
+        }
+        let live = self.live.get_mut().unwrap_or_else(PoisonError::into_inner);
+        assert!(
+            live.is_empty(),
+            "{} allocation(s) leaked: {live:?}",
+            live.len()
+        );
+    }
+}
+
+impl LeakCheckAllocator {
+    /// Tracks allocations made through [`Global`].
+    pub fn new() -> Self {
+        Self::with_allocator(Global)
+    }
+}
+
+impl Default for LeakCheckAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Allocator> LeakCheckAllocator<A> {
+    /// Tracks allocations made through `inner`.
+    pub fn with_allocator(inner: A) -> Self {
+        Self {
+            inner,
+            state: Arc::default(),
+        }
+    }
+
+    /// Returns the number of live allocations.
+    pub fn outstanding(&self) -> usize {
+        self.state.live().len()
+    }
+
+    /// Returns the total size in bytes of all live allocations.
+    pub fn outstanding_bytes(&self) -> usize {
+        self.state
+            .live()
+            .iter()
+            .map(|(_, layout)| layout.size())
+            .sum()
+    }
+
+    /// Panics if any allocation is still live.
+    ///
+    /// # Panics
+    ///
+    /// Panics listing the address and layout of every live allocation.
+    #[track_caller]
+    pub fn assert_no_leaks(&self) {
+        let live = self.state.live();
+        assert!(
+            live.is_empty(),
+            "{} allocation(s) leaked: {live:?}",
+            live.len()
+        );
+    }
+
+    fn track(
+        &self,
+        layout: Layout,
+        allocation: Result<NonNull<[u8]>, AllocError>,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let ptr = allocation?;
+        self.state.live().push((ptr.addr().get(), layout));
+        Ok(ptr)
+    }
+}
+
+// SAFETY:
+// - all memory is allocated and freed by `inner`; this wrapper only records
+//   live allocations.
+unsafe impl<A: Allocator> Allocator for LeakCheckAllocator<A> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.track(layout, self.inner.allocate(layout))
+    }
+
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.track(layout, self.inner.allocate_zeroed(layout))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        {
+            let mut live = self.state.live();
+            let addr = ptr.addr().get();
+            let Some(index) = live.iter().position(|&(a, _)| a == addr) else {
+                drop(live);
+                panic!("deallocating untracked pointer {ptr:p} with {layout:?}");
+            };
+            let (_, allocated) = live[index];
+            if allocated != layout {
+                drop(live);
+                panic!(
+                    "layout mismatch for {ptr:p}: allocated with {allocated:?}, deallocated with {layout:?}"
+                );
+            }
+            live.swap_remove(index);
+        }
+        // SAFETY: upheld by the caller; `ptr` was allocated by `inner` with `layout`.
+        unsafe { self.inner.deallocate(ptr, layout) }
+    }
+}
@@ -8,13 +8,15 @@
 //!
 //! Two tensors are broadcast-able if:
 //!
-//! - Each tensor has non-zero [`Shape::ndims`]
 //! - While iterating over dimensions in reverse, the dimension sizes
 //!   are either:
 //!     - Equal
 //!     - 1
 //!     - Do not exist
 //!
+//! A scalar shape ([`Shape::scalar`]) has no dimensions, so it broadcasts
+//! with any shape.
+//!
 //! If two tensors are broadcast-able, the dimensions of the result
 //! from their operation is as follows:
 //!
@@ -27,12 +29,33 @@
 //!    - B: `[..., K, N]`
 //!    - output: `[...broadcasted, M, N]`
 
-use crate::error::TensorError;
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::error::{OpContext, TensorError};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Shape(Box<[usize]>);
 
 impl Shape {
+    /// Returns the rank-0 shape of a scalar, with volume 1.
+    ///
+    /// ```
+    /// use autodiff::{shape, shape::Shape};
+    ///
+    /// let s = Shape::scalar();
+    /// assert!(s.is_scalar());
+    /// assert_eq!(s.volume(), 1);
+    /// assert_eq!(s, shape![]);
+    /// ```
+    pub fn scalar() -> Self {
+        Self(Box::new([]))
+    }
+
+    /// Returns `true` if the shape has no dimensions.
+    pub fn is_scalar(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn ndims(&self) -> usize {
         self.0.len()
     }
@@ -62,46 +85,114 @@ impl Shape {
     ///
     /// # Panics
     ///
-    /// Panics in debug profile if `indices.len() != self.ndims()`
+    /// Panics if `indices` is out of bounds. See [`Shape::try_linear_index`].
     pub fn linear_index(&self, indices: &[usize]) -> usize {
-        debug_assert_eq!(indices.len(), self.ndims());
+        self.try_linear_index(indices).expect("invalid indices")
+    }
+
+    /// Returns the linear index from a given N dim index.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TensorError::IndexOutOfBounds`] if `indices.len() != self.ndims()`,
+    /// any index is not smaller than its dimension, or the linear index
+    /// overflows `usize`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use autodiff::{error::TensorError, shape};
+    ///
+    /// let s = shape![2, 3, 4];
+    /// assert_eq!(s.try_linear_index(&[1, 2, 3]).unwrap(), 23);
+    /// assert!(matches!(
+    ///     s.try_linear_index(&[1, 3, 0]),
+    ///     Err(TensorError::IndexOutOfBounds { .. })
+    /// ));
+    /// assert!(shape![usize::MAX, 3].try_linear_index(&[usize::MAX - 1, 2]).is_err());
+    /// assert!(shape![3, usize::MAX].try_linear_index(&[2, 0]).is_err());
+    /// ```
+    pub fn try_linear_index(&self, indices: &[usize]) -> Result<usize, TensorError> {
+        let out_of_bounds = || TensorError::IndexOutOfBounds {
+            index: indices.into(),
+            shape: self.clone(),
+        };
+
+        if indices.len() != self.ndims() {
+            return Err(out_of_bounds());
+        }
 
         indices
             .iter()
             .zip(&self.0)
-            .try_fold(0, |acc, (dim, i)| (i < dim).then_some(acc * dim + i))
-            .expect("invalid indices")
+            .try_fold(0usize, |acc, (&index, &dim)| {
+                if index >= dim {
+                    return None;
+                }
+                acc.checked_mul(dim)?.checked_add(index)
+            })
+            .ok_or_else(out_of_bounds)
+    }
+
+    /// Returns the shape resulting from broadcasting `self` with `other` elementwise.
+    ///
+    /// For broadcasting semantics, see: [`mod@crate::shape`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a dimension pair is neither equal nor `1`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use autodiff::{shape, shape::Shape};
+    ///
+    /// assert_eq!(shape![2, 1, 3].broadcast(&shape![4, 1]).unwrap(), shape![2, 4, 3]);
+    /// assert_eq!(Shape::scalar().broadcast(&shape![5]).unwrap(), shape![5]);
+    /// assert!(shape![2, 3].broadcast(&shape![4]).is_err());
+    /// ```
+    pub fn broadcast(&self, other: &Self) -> Result<Self, TensorError> {
+        let dims = try_broadcast(self.dims(), other.dims())?;
+        Ok(Shape(dims.into_boxed_slice()))
     }
 
     /// Checks if `Self` can matrix multiply with `other` after broadcasting.
     ///
-    /// For general broadcasting semantics, see: [`crate::shape`]
+    /// For general broadcasting semantics, see: [`mod@crate::shape`]
     ///
     /// # Errors
     ///
     /// Returns an error if either self or other are of length 0 or cannot be broadcasted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use autodiff::shape;
+    ///
+    /// let out = shape![2, 1, 3, 4].can_broadcast_matmul(&shape![5, 4, 6]).unwrap();
+    /// assert_eq!(out, shape![2, 5, 3, 6]);
+    /// ```
     pub fn can_broadcast_matmul(&self, other: &Self) -> Result<Self, TensorError> {
         let (a, b) = (self.dims(), other.dims());
 
+        let mismatch = || TensorError::ShapeMismatch(OpContext::new("matmul", self, other));
+
         if a.is_empty() || b.is_empty() {
-            return Err(TensorError::InvalidOp(
-                "matmul requires at least 1D tensors".to_string(),
-            ));
+            return Err(mismatch());
         }
 
         let a_last = a[a.len().saturating_sub(1)];
 
         let b_snd_last = if b.len() == 1 { b[0] } else { b[b.len() - 2] };
         if a_last != b_snd_last {
-            return Err(TensorError::InvalidOp(format!(
-                "cannot matmul\na: {a:?}\nb: {b:?}"
-            )));
+            return Err(mismatch());
         }
 
         let mut output = try_broadcast(
             &a[..a.len().saturating_sub(2)],
             &b[..b.len().saturating_sub(2)],
-        )?;
+        )
+        .map_err(|_| mismatch())?;
 
         let m = if a.len() >= 2 { a[a.len() - 2] } else { 1 };
         let n = if b.len() >= 2 { b[b.len() - 1] } else { 1 };
@@ -128,24 +219,78 @@ fn try_broadcast(a: &[usize], b: &[usize]) -> Result<Vec<usize>, TensorError> {
         }
     }
 
+    ret.reverse();
     Ok(ret)
 }
 
+/// Builds a [`Shape`] from a list of dimensions.
+///
+/// ```
+/// use autodiff::shape;
+///
+/// let s = shape![2, 3, 4];
+/// assert_eq!(s.dims(), &[2, 3, 4]);
+/// ```
+#[macro_export]
+macro_rules! shape {
+    ($($dim:expr),* $(,)?) => {
+        $crate::shape::Shape::from([$($dim),*])
+    };
+}
+
 impl From<&[usize]> for Shape {
     fn from(value: &[usize]) -> Self {
         Self(value.to_vec().into_boxed_slice())
     }
 }
 
-impl std::ops::Index<usize> for Shape {
+impl From<Vec<usize>> for Shape {
+    fn from(value: Vec<usize>) -> Self {
+        Self(value.into_boxed_slice())
+    }
+}
+
+impl<const N: usize> From<[usize; N]> for Shape {
+    fn from(value: [usize; N]) -> Self {
+        Self(Box::new(value))
+    }
+}
+
+impl From<usize> for Shape {
+    fn from(value: usize) -> Self {
+        Self(Box::new([value]))
+    }
+}
+
+macro_rules! impl_from_tuple {
+    ($($dim:ident),+) => {
+        impl From<($(impl_from_tuple!(@usize $dim),)+)> for Shape {
+            fn from(($($dim,)+): ($(impl_from_tuple!(@usize $dim),)+)) -> Self {
+                Self(Box::new([$($dim),+]))
+            }
+        }
+    };
+    (@usize $dim:ident) => { usize };
+}
+
+impl_from_tuple!(d0);
+impl_from_tuple!(d0, d1);
+impl_from_tuple!(d0, d1, d2);
+impl_from_tuple!(d0, d1, d2, d3);
+impl_from_tuple!(d0, d1, d2, d3, d4);
+impl_from_tuple!(d0, d1, d2, d3, d4, d5);
+impl_from_tuple!(d0, d1, d2, d3, d4, d5, d6);
+impl_from_tuple!(d0, d1, d2, d3, d4, d5, d6, d7);
+
+impl core::ops::Index<usize> for Shape {
     type Output = usize;
     fn index(&self, index: usize) -> &Self::Output {
         &self.0[index]
     }
 }
 
-impl std::fmt::Display for Shape {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Shape({:?})", &self.0)
+impl core::fmt::Display for Shape {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Shape({:?})", self.0)
     }
 }
@@ -1,25 +1,31 @@
 //! Provides raw, aligned memory storage [`Storage`] for tensor data.
 //! Handles allocation, deallocation, and basic access, with memory alignment.
 
+use alloc::vec::Vec;
+
+use crate::error::TensorError;
 use crate::memory::policy::SimdAlignment;
 
-use crate::memory::buffer::{Buffer, BufferBuilder};
+use crate::memory::{Buffer, BufferBuilder};
+
+pub mod bits;
+pub mod small;
 
 /// `Storage<T, A>` is a partially-initialized memory container.
 ///
 /// It wraps [`Buffer<T, A>`], which handles allocation and layout.
 /// - The uninitialized tail (if any) of the `Buffer` is never exposed directly.
-pub struct Storage<T, A = std::alloc::Global>
+pub struct Storage<T, A = alloc::alloc::Global>
 where
-    A: std::alloc::Allocator + Clone,
+    A: core::alloc::Allocator + Clone,
 {
-    /// See [`crate::buffer::Buffer`].
+    /// See [`crate::memory::Buffer`].
     buffer: Buffer<T, A>,
     /// The number of elements guaranteed to be initialized.
     init: usize,
 }
 
-impl<T, A: std::alloc::Allocator + Clone> Storage<T, A> {
+impl<T, A: core::alloc::Allocator + Clone> Storage<T, A> {
     /// Creates a new storage buffer for `numel` elements using the given allocator.
     ///
     /// Allocated memory is uninitialized. no elements are considered initialized yet.
@@ -28,6 +34,124 @@ impl<T, A: std::alloc::Allocator + Clone> Storage<T, A> {
         Self { buffer, init: 0 }
     }
 
+    /// Fallible version of [`Storage::new`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TensorError::CapacityOverflow`] if the allocation size
+    /// overflows, or [`TensorError::AllocationFailed`] if the allocator fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is a zero-sized type or `numel` is 0.
+    pub fn try_new(numel: usize, alloc: A) -> Result<Self, TensorError> {
+        let buffer: Buffer<T, A> =
+            BufferBuilder::<_, SimdAlignment>::new(numel).try_build(alloc)?;
+        Ok(Self { buffer, init: 0 })
+    }
+
+    /// Creates a storage that takes ownership of an existing allocation
+    /// without copying.
+    ///
+    /// # Safety
+    ///
+    /// - All requirements of [`Buffer::from_raw_parts`] must hold.
+    /// - `init <= numel`, and elements `[0, init)` must be initialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use std::alloc::Global;
+    ///
+    /// use autodiff::storage::Storage;
+    ///
+    /// let s = Storage::from_slice(&[1.0f32, 2.0, 3.0], Global);
+    /// let (ptr, numel, init, layout, alloc) = s.into_raw_parts();
+    /// // SAFETY: the parts come straight from `into_raw_parts`.
+    /// let s = unsafe { Storage::from_raw_parts(ptr, numel, init, layout, alloc) };
+    /// assert_eq!(s.as_slice(), [1.0, 2.0, 3.0]);
+    /// ```
+    pub unsafe fn from_raw_parts(
+        ptr: core::ptr::NonNull<T>,
+        numel: usize,
+        init: usize,
+        layout: core::alloc::Layout,
+        alloc: A,
+    ) -> Self {
+        debug_assert!(init <= numel);
+        // SAFETY: upheld by the caller.
+        let buffer = unsafe { Buffer::from_raw_parts(ptr, numel, layout, alloc) };
+        Self { buffer, init }
+    }
+
+    /// Decomposes the storage into `(ptr, numel, init, layout, allocator)`
+    /// without dropping any elements or deallocating.
+    ///
+    /// The caller becomes responsible for the `init` initialized elements and
+    /// the allocation, e.g. by passing the parts back to [`Storage::from_raw_parts`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use std::alloc::Global;
+    ///
+    /// use autodiff::storage::Storage;
+    ///
+    /// let s = Storage::from_slice(&["a".to_string(), "b".to_string()], Global);
+    /// let (ptr, numel, init, layout, alloc) = s.into_raw_parts();
+    /// assert_eq!(init, 2);
+    ///
+    /// // The strings are owned by the raw parts now, and dropped exactly once
+    /// // by the rebuilt storage.
+    /// // SAFETY: the parts come straight from `into_raw_parts`.
+    /// let s = unsafe { Storage::from_raw_parts(ptr, numel, init, layout, alloc) };
+    /// assert_eq!(s.as_slice(), ["a", "b"]);
+    /// ```
+    pub fn into_raw_parts(self) -> (core::ptr::NonNull<T>, usize, usize, core::alloc::Layout, A) {
+        let this = core::mem::ManuallyDrop::new(self);
+        // SAFETY:
+        // - `this` is never dropped, so the buffer is moved out exactly once.
+        let buffer = unsafe { core::ptr::read(&raw const this.buffer) };
+        let (ptr, numel, layout, alloc) = buffer.into_raw_parts();
+        (ptr, numel, this.init, layout, alloc)
+    }
+
+    /// Moves the initialized elements `[0, init)` out into a `Vec<T>`.
+    ///
+    /// The elements are moved with a single bitwise copy, since the aligned
+    /// allocation cannot be handed to `Vec` directly. The original allocation
+    /// is then released without dropping any element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use std::alloc::Global;
+    ///
+    /// use autodiff::storage::Storage;
+    ///
+    /// let s = Storage::from_slice(&["a".to_string(), "b".to_string()], Global);
+    /// let v = s.into_vec();
+    /// assert_eq!(v, ["a", "b"]);
+    /// ```
+    pub fn into_vec(mut self) -> Vec<T> {
+        let len = self.init;
+        let mut vec = Vec::with_capacity(len);
+        // SAFETY:
+        // - `self.as_ptr()` is valid for reads of `len` initialized elements.
+        // - `vec` has capacity for `len` elements and does not overlap `self`.
+        // - ownership of the elements moves to `vec`; `init` is reset below so
+        //   they are not dropped twice.
+        unsafe {
+            core::ptr::copy_nonoverlapping(self.as_ptr(), vec.as_mut_ptr(), len);
+            vec.set_len(len);
+        }
+        self.init = 0;
+        vec
+    }
+
     /// Returns a reference to the element at `index` if it has been initialized.
     ///
     /// Returns `None` if `index >= self.init`.
@@ -37,11 +161,11 @@ impl<T, A: std::alloc::Allocator + Clone> Storage<T, A> {
         }
 
         // SAFETY:
-        // - `buffer.as_ptr()` is a valid, non-null, aligned pointer to
+        // - `buffer.as_non_null()` is a valid, non-null, aligned pointer to
         //   a allocated buffer.
         // - index is bounds-checked against init, and init guarantees
         //   that elements [0..init) are properly initialized.
-        unsafe { self.buffer.as_ptr().add(index).as_ref() }
+        Some(unsafe { self.buffer.as_non_null().add(index).as_ref() })
     }
 
     /// writes a value to the next uninitialized slot, extending `init` by 1.
@@ -53,10 +177,10 @@ impl<T, A: std::alloc::Allocator + Clone> Storage<T, A> {
     pub unsafe fn write_unchecked(&mut self, value: T) {
         debug_assert!(self.init < self.allocated_len());
         // SAFETY:
-        // - `self.as_mut_ptr()` is a valid, non-null, aligned pointer.
+        // - `buffer.as_non_null()` is a valid, non-null, aligned pointer.
         // - `self.init` < `self.allocated_len()`
         unsafe {
-            std::ptr::write(self.as_mut_ptr().add(self.init), value);
+            self.buffer.as_non_null().add(self.init).write(value);
         }
         self.init += 1;
     }
@@ -84,7 +208,7 @@ impl<T, A: std::alloc::Allocator + Clone> Storage<T, A> {
             // - `val` is cloned beforehand so panic is separated from
             //   the write.
             unsafe {
-                std::ptr::drop_in_place(self.buffer.as_mut_ptr().add(i));
+                self.buffer.as_non_null().add(i).drop_in_place();
             }
         }
         self.init = 0;
@@ -98,11 +222,11 @@ impl<T, A: std::alloc::Allocator + Clone> Storage<T, A> {
             return None;
         }
         // SAFETY:
-        // - `buffer.as_mut_ptr()` is a valid, non-null, aligned pointer to
+        // - `buffer.as_non_null()` is a valid, non-null, aligned pointer to
         //   a allocated buffer.
         // - index is bounds-checked against init, and init guarantees
         //   that elements [0..init) are properly initialized.
-        unsafe { self.buffer.as_mut_ptr().add(index).as_mut() }
+        Some(unsafe { self.buffer.as_non_null().add(index).as_mut() })
     }
 
     /// Returns the number of initialized elements.
@@ -156,7 +280,7 @@ impl<T, A: std::alloc::Allocator + Clone> Storage<T, A> {
         //   allocated memory.
         // - `self.init` is a valid number of initialized elements
         //   within the allocated region.
-        unsafe { std::slice::from_raw_parts(self.as_ptr(), self.init) }
+        unsafe { core::slice::from_raw_parts(self.as_ptr(), self.init) }
     }
 
     /// Returns a mutable slice of all initialized elements `[0, init)`.
@@ -170,16 +294,53 @@ impl<T, A: std::alloc::Allocator + Clone> Storage<T, A> {
         //   allocated memory.
         // - `self.init` is a valid number of initialized elements
         //   within the allocated region.
-        unsafe { std::slice::from_raw_parts_mut(self.as_mut_ptr(), self.init) }
+        unsafe { core::slice::from_raw_parts_mut(self.as_mut_ptr(), self.init) }
+    }
+
+    /// Splits the initialized elements into disjoint mutable chunks whose
+    /// starts are all aligned like the buffer itself.
+    ///
+    /// `n` is rounded up to the smallest multiple that keeps every chunk
+    /// start aligned; only the last chunk may be shorter. Chunks are `Send`
+    /// when `T` is, so they can be handed to threads or SIMD kernels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use std::alloc::Global;
+    ///
+    /// use autodiff::storage::Storage;
+    ///
+    /// let mut s = Storage::filled_with(1000, 1.0f32, Global);
+    /// std::thread::scope(|scope| {
+    ///     for chunk in s.par_chunks_aligned(256) {
+    ///         scope.spawn(move || chunk.iter_mut().for_each(|x| *x *= 2.0));
+    ///     }
+    /// });
+    /// assert!(s.as_slice().iter().all(|&x| x == 2.0));
+    /// ```
+    pub fn par_chunks_aligned(&mut self, n: usize) -> core::slice::ChunksMut<'_, T> {
+        assert!(n != 0, "chunk size must be non-zero");
+        // Smallest element count spanning a multiple of the alignment. The
+        // alignment is a power of two, so only the size's factors of two matter.
+        let align = self.buffer.layout().align();
+        let size = core::mem::size_of::<T>();
+        let step = align >> size.trailing_zeros().min(align.trailing_zeros());
+        self.as_mut_slice().chunks_mut(n.next_multiple_of(step))
     }
 }
 
-impl<T: Clone, A: std::alloc::Allocator + Clone> Storage<T, A> {
+impl<T: Clone, A: core::alloc::Allocator + Clone> Storage<T, A> {
     /// Creates a new storage buffer and clones each element from the given slice.
     ///
     /// All elements are immediately initialized.
     pub fn from_slice(slice: &[T], alloc: A) -> Self {
-        let mut buffer: Buffer<T, _> = {
+        let buffer: Buffer<T, _> = {
             let numel = slice.len();
             BufferBuilder::<_, SimdAlignment>::new(numel).build(alloc)
         };
@@ -192,7 +353,7 @@ impl<T: Clone, A: std::alloc::Allocator + Clone> Storage<T, A> {
             // - `val` is cloned beforehand so panic is separated from
             //   the write.
             unsafe {
-                std::ptr::write(buffer.as_mut_ptr().add(i), val);
+                buffer.as_non_null().add(i).write(val);
             }
             init += 1;
         }
@@ -203,7 +364,7 @@ impl<T: Clone, A: std::alloc::Allocator + Clone> Storage<T, A> {
     ///
     /// All elements are immediately initialized.
     pub fn filled_with(numel: usize, value: T, alloc: A) -> Self {
-        let mut buffer: Buffer<T, _> = BufferBuilder::<_, SimdAlignment>::new(numel).build(alloc);
+        let buffer: Buffer<T, _> = BufferBuilder::<_, SimdAlignment>::new(numel).build(alloc);
         let mut init = 0;
         for i in 0..numel {
             let val = value.clone();
@@ -213,7 +374,7 @@ impl<T: Clone, A: std::alloc::Allocator + Clone> Storage<T, A> {
             // - `val` is cloned beforehand so panic is separated from
             //   the write.
             unsafe {
-                std::ptr::write(buffer.as_mut_ptr().add(i), val);
+                buffer.as_non_null().add(i).write(val);
             }
             init += 1;
         }
@@ -222,16 +383,84 @@ impl<T: Clone, A: std::alloc::Allocator + Clone> Storage<T, A> {
     }
 }
 
-impl<T, A: std::alloc::Allocator + Clone> Drop for Storage<T, A> {
+/// Number of leading and trailing elements shown by the [`Debug`](core::fmt::Debug) preview.
+const DEBUG_PREVIEW: usize = 3;
+
+impl<T: core::fmt::Debug, A: core::alloc::Allocator + Clone> core::fmt::Debug for Storage<T, A> {
+    /// Shows `len/capacity`, alignment and a preview of the initialized elements.
+    ///
+    /// Storages longer than twice `DEBUG_PREVIEW` only show their first and
+    /// last elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// use std::alloc::Global;
+    ///
+    /// use autodiff::{
+    ///     memory::policy::{AlignmentStrategy, SimdAlignment},
+    ///     storage::Storage,
+    /// };
+    ///
+    /// // Padding and alignment depend on the SIMD target.
+    /// let align = SimdAlignment::alignment::<u8>();
+    ///
+    /// let short = Storage::from_slice(&[1u8, 2, 3], Global);
+    /// assert_eq!(
+    ///     format!("{short:?}"),
+    ///     format!(
+    ///         "Storage {{ len: 3/3, allocated_len: {}, align: {align}, data: [1, 2, 3], .. }}",
+    ///         short.allocated_len(),
+    ///     ),
+    /// );
+    ///
+    /// let long = Storage::from_slice(&[0u8, 1, 2, 3, 4, 5, 6, 7, 8, 9], Global);
+    /// assert_eq!(
+    ///     format!("{long:?}"),
+    ///     format!(
+    ///         "Storage {{ len: 10/10, allocated_len: {}, align: {align}, \
+    ///          data: [0, 1, 2, ..., 7, 8, 9], .. }}",
+    ///         long.allocated_len(),
+    ///     ),
+    /// );
+    /// ```
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        struct Preview<'a, T>(&'a [T]);
+
+        impl<T: core::fmt::Debug> core::fmt::Debug for Preview<'_, T> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                let data = self.0;
+                if data.len() <= 2 * DEBUG_PREVIEW {
+                    return f.debug_list().entries(data).finish();
+                }
+                f.debug_list()
+                    .entries(&data[..DEBUG_PREVIEW])
+                    .entry(&format_args!("..."))
+                    .entries(&data[data.len() - DEBUG_PREVIEW..])
+                    .finish()
+            }
+        }
+
+        f.debug_struct("Storage")
+            .field("len", &format_args!("{}/{}", self.len(), self.capacity()))
+            .field("allocated_len", &self.allocated_len())
+            .field("align", &self.buffer.layout().align())
+            .field("data", &Preview(self.as_slice()))
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, A: core::alloc::Allocator + Clone> Drop for Storage<T, A> {
     fn drop(&mut self) {
         // Drop all initialized elements
         for i in 0..self.init {
             // SAFETY:
-            // - `buffer.as_mut_ptr()` is a valid, aligned non-null pointer.
+            // - `buffer.as_non_null()` is a valid, aligned non-null pointer.
             // - `ptr + i` is valid within initialized elements.
             // - `T` at `ptr + i` is initialized.
             unsafe {
-                std::ptr::drop_in_place(self.buffer.as_mut_ptr().add(i));
+                self.buffer.as_non_null().add(i).drop_in_place();
             }
         }
     }
@@ -0,0 +1,285 @@
+//! Bit-packed boolean storage [`BitStorage`].
+//!
+//! Stores one element per bit instead of one per byte, which matters for
+//! large boolean masks. Bits are packed LSB-first into `u64` words held in a
+//! SIMD-aligned [`Buffer`], so the word-wise kernels vectorize well.
+
+use alloc::alloc::Global;
+use core::alloc::Allocator;
+
+use crate::error::TensorError;
+use crate::memory::policy::SimdAlignment;
+use crate::memory::{Buffer, BufferBuilder};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Number of leading and trailing bits shown by the [`Debug`](core::fmt::Debug) preview.
+const DEBUG_PREVIEW_BITS: usize = 32;
+
+/// Fully-initialized, bit-packed storage for `len` booleans.
+///
+/// The unused high bits of the last word are kept at zero by every
+/// operation, so word-level kernels never need to special-case the tail.
+///
+/// # Examples
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use std::alloc::Global;
+///
+/// use autodiff::{error::TensorError, storage::bits::BitStorage};
+///
+/// let mut mask = BitStorage::from_bools(&[true, true, false, false, true], Global);
+/// let other = BitStorage::from_bools(&[true, false, true, false, true], Global);
+///
+/// mask.and_assign(&other).unwrap();
+/// assert_eq!(mask.iter().collect::<Vec<_>>(), [true, false, false, false, true]);
+///
+/// mask.or_assign(&other).unwrap();
+/// assert_eq!(mask.count_ones(), 3);
+///
+/// // Tail bits past `len` stay zero, so counts only see stored booleans.
+/// mask.not_assign();
+/// assert_eq!(mask.count_ones(), 2);
+/// assert_eq!(BitStorage::filled_with(70, true, Global).count_ones(), 70);
+///
+/// let short = BitStorage::new(3, Global);
+/// assert!(matches!(
+///     mask.xor_assign(&short),
+///     Err(TensorError::InconsistentDims { .. })
+/// ));
+/// ```
+pub struct BitStorage<A = Global>
+where
+    A: Allocator + Clone,
+{
+    /// Packed words. Every word is initialized.
+    words: Buffer<u64, A>,
+    /// Number of booleans stored.
+    len: usize,
+}
+
+impl<A: Allocator + Clone> BitStorage<A> {
+    /// Creates storage for `len` booleans, all set to `false`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is 0 or the allocation fails.
+    pub fn new(len: usize, alloc: A) -> Self {
+        let words = BufferBuilder::<_, SimdAlignment>::new(len.div_ceil(WORD_BITS))
+            .zeroed()
+            .build(alloc);
+        Self { words, len }
+    }
+
+    /// Fallible version of [`BitStorage::new`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TensorError::CapacityOverflow`] if the allocation size
+    /// overflows, or [`TensorError::AllocationFailed`] if the allocator fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is 0.
+    pub fn try_new(len: usize, alloc: A) -> Result<Self, TensorError> {
+        let words = BufferBuilder::<_, SimdAlignment>::new(len.div_ceil(WORD_BITS))
+            .zeroed()
+            .try_build(alloc)?;
+        Ok(Self { words, len })
+    }
+
+    /// Creates storage for `len` booleans, all set to `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is 0 or the allocation fails.
+    pub fn filled_with(len: usize, value: bool, alloc: A) -> Self {
+        let mut bits = Self::new(len, alloc);
+        if value {
+            bits.words_mut().fill(u64::MAX);
+            bits.clear_tail();
+        }
+        bits
+    }
+
+    /// Creates storage holding a packed copy of `values`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` is empty or the allocation fails.
+    pub fn from_bools(values: &[bool], alloc: A) -> Self {
+        let mut bits = Self::new(values.len(), alloc);
+        for (word, chunk) in bits.words_mut().iter_mut().zip(values.chunks(WORD_BITS)) {
+            *word = chunk
+                .iter()
+                .enumerate()
+                .fold(0, |acc, (i, &bit)| acc | (u64::from(bit) << i));
+        }
+        bits
+    }
+
+    /// Returns the number of booleans stored.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no booleans are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the bit at `index`, or `None` if `index >= len()`.
+    pub fn get(&self, index: usize) -> Option<bool> {
+        (index < self.len).then(|| self.bit(index))
+    }
+
+    /// Sets the bit at `index` to `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len()`.
+    pub fn set(&mut self, index: usize, value: bool) {
+        assert!(
+            index < self.len,
+            "index {index} out of bounds for {} bits",
+            self.len
+        );
+        let (word, mask) = (index / WORD_BITS, 1 << (index % WORD_BITS));
+        if value {
+            self.words_mut()[word] |= mask;
+        } else {
+            self.words_mut()[word] &= !mask;
+        }
+    }
+
+    /// Returns the number of bits set to `true`.
+    pub fn count_ones(&self) -> usize {
+        self.words().iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Returns an iterator over all stored booleans.
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(|i| self.bit(i))
+    }
+
+    /// Returns the packed words, LSB-first. Unused tail bits are zero.
+    pub fn words(&self) -> &[u64] {
+        // SAFETY:
+        // - the buffer is allocated zeroed, so every word is initialized.
+        unsafe { self.words.as_slice() }
+    }
+
+    /// Sets `self = self & other`, elementwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `self.len() != other.len()`.
+    pub fn and_assign<B: Allocator + Clone>(
+        &mut self,
+        other: &BitStorage<B>,
+    ) -> Result<(), TensorError> {
+        self.zip_words(other, |a, b| a & b)
+    }
+
+    /// Sets `self = self | other`, elementwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `self.len() != other.len()`.
+    pub fn or_assign<B: Allocator + Clone>(
+        &mut self,
+        other: &BitStorage<B>,
+    ) -> Result<(), TensorError> {
+        self.zip_words(other, |a, b| a | b)
+    }
+
+    /// Sets `self = self ^ other`, elementwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `self.len() != other.len()`.
+    pub fn xor_assign<B: Allocator + Clone>(
+        &mut self,
+        other: &BitStorage<B>,
+    ) -> Result<(), TensorError> {
+        self.zip_words(other, |a, b| a ^ b)
+    }
+
+    /// Sets `self = !self`, elementwise.
+    pub fn not_assign(&mut self) {
+        for word in self.words_mut() {
+            *word = !*word;
+        }
+        self.clear_tail();
+    }
+
+    fn zip_words<B: Allocator + Clone>(
+        &mut self,
+        other: &BitStorage<B>,
+        f: impl Fn(u64, u64) -> u64,
+    ) -> Result<(), TensorError> {
+        if self.len != other.len {
+            return Err(TensorError::inconsistent(&[self.len], &[other.len]));
+        }
+        for (a, &b) in self.words_mut().iter_mut().zip(other.words()) {
+            *a = f(*a, b);
+        }
+        Ok(())
+    }
+
+    fn bit(&self, index: usize) -> bool {
+        (self.words()[index / WORD_BITS] >> (index % WORD_BITS)) & 1 == 1
+    }
+
+    fn words_mut(&mut self) -> &mut [u64] {
+        // SAFETY:
+        // - the buffer is allocated zeroed, so every word is initialized.
+        unsafe { self.words.as_slice_mut() }
+    }
+
+    /// Zeroes the unused high bits of the last word.
+    fn clear_tail(&mut self) {
+        let used = self.len % WORD_BITS;
+        if used != 0
+            && let Some(last) = self.words_mut().last_mut()
+        {
+            *last &= (1 << used) - 1;
+        }
+    }
+}
+
+impl<A: Allocator + Clone> core::fmt::Debug for BitStorage<A> {
+    /// Shows `len`, the number of set bits and a `0`/`1` preview.
+    ///
+    /// Storages longer than twice `DEBUG_PREVIEW_BITS` only show their first
+    /// and last bits.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        struct Preview<'a, A: Allocator + Clone>(&'a BitStorage<A>);
+
+        impl<A: Allocator + Clone> core::fmt::Debug for Preview<'_, A> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                let bits = self.0;
+                let write_range =
+                    |f: &mut core::fmt::Formatter<'_>, range: core::ops::Range<usize>| {
+                        for i in range {
+                            f.write_str(if bits.bit(i) { "1" } else { "0" })?;
+                        }
+                        Ok(())
+                    };
+                if bits.len <= 2 * DEBUG_PREVIEW_BITS {
+                    return write_range(f, 0..bits.len);
+                }
+                write_range(f, 0..DEBUG_PREVIEW_BITS)?;
+                f.write_str("...")?;
+                write_range(f, bits.len - DEBUG_PREVIEW_BITS..bits.len)
+            }
+        }
+
+        f.debug_struct("BitStorage")
+            .field("len", &self.len)
+            .field("ones", &self.count_ones())
+            .field("bits", &Preview(self))
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,229 @@
+//! Small-size-optimized storage [`SmallStorage`].
+//!
+//! Scalars and tiny gradients are produced every training step, and heap
+//! allocating each of them costs more than the data itself. [`SmallStorage`]
+//! keeps up to `N` elements inline and only falls back to a heap-allocated
+//! [`Storage`] for larger data.
+
+use alloc::alloc::Global;
+use core::{alloc::Allocator, mem::MaybeUninit};
+
+use super::Storage;
+
+/// Inline element array, aligned for the widest SIMD target (AVX2).
+#[repr(C, align(32))]
+struct Inline<T, const N: usize> {
+    data: [MaybeUninit<T>; N],
+    /// The number of elements guaranteed to be initialized.
+    len: usize,
+}
+
+enum Repr<T, const N: usize, A: Allocator + Clone> {
+    /// The allocator is kept so the data can be moved to the heap later.
+    Inline(Inline<T, N>, A),
+    Heap(Storage<T, A>),
+}
+
+/// Fully-initialized storage that holds up to `N` elements inline.
+///
+/// - Data of at most `N` elements lives inside the value itself and never
+///   touches the allocator.
+/// - Larger data is stored in a [`Storage<T, A>`] allocated from `alloc`.
+///
+/// `N` is an element count. For a byte threshold, pick `N` such that
+/// `N * size_of::<T>()` stays within budget; [`SmallStorage::INLINE_BYTES`]
+/// reports the resulting size.
+///
+/// # Examples
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// use std::alloc::Global;
+///
+/// use autodiff::storage::small::SmallStorage;
+///
+/// let loss = SmallStorage::<f32, 4>::filled_with(1, 0.25, Global);
+/// assert!(loss.is_inline());
+///
+/// let grad = SmallStorage::<f32, 4>::from_slice(&[1.0; 16], Global);
+/// assert!(!grad.is_inline());
+/// ```
+pub struct SmallStorage<T, const N: usize, A = Global>
+where
+    A: Allocator + Clone,
+{
+    repr: Repr<T, N, A>,
+}
+
+impl<T, const N: usize, A: Allocator + Clone> SmallStorage<T, N, A> {
+    /// Number of bytes available for inline elements.
+    pub const INLINE_BYTES: usize = N * size_of::<T>();
+
+    /// Returns the number of elements stored.
+    pub fn len(&self) -> usize {
+        match &self.repr {
+            Repr::Inline(inline, _) => inline.len,
+            Repr::Heap(storage) => storage.len(),
+        }
+    }
+
+    /// Returns `true` if no elements are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the elements are stored inline.
+    pub fn is_inline(&self) -> bool {
+        matches!(self.repr, Repr::Inline(..))
+    }
+
+    /// Returns a shared slice of all elements.
+    pub fn as_slice(&self) -> &[T] {
+        match &self.repr {
+            // SAFETY:
+            // - elements `[0, len)` of the inline array are initialized.
+            Repr::Inline(inline, _) => unsafe {
+                core::slice::from_raw_parts(inline.data.as_ptr().cast(), inline.len)
+            },
+            Repr::Heap(storage) => storage.as_slice(),
+        }
+    }
+
+    /// Returns a mutable slice of all elements.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        match &mut self.repr {
+            // SAFETY:
+            // - elements `[0, len)` of the inline array are initialized.
+            Repr::Inline(inline, _) => unsafe {
+                core::slice::from_raw_parts_mut(inline.data.as_mut_ptr().cast(), inline.len)
+            },
+            Repr::Heap(storage) => storage.as_mut_slice(),
+        }
+    }
+
+    /// Converts into a heap-allocated [`Storage`], moving inline elements to
+    /// a new allocation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if inline elements must be moved and the allocation fails.
+    pub fn into_storage(self) -> Storage<T, A> {
+        match self.repr {
+            Repr::Inline(mut inline, alloc) => {
+                let len = inline.len;
+                let mut storage = Storage::new(len, alloc);
+                // SAFETY:
+                // - elements `[0, len)` of the inline array are initialized,
+                //   and `storage` is allocated for `len` elements.
+                // - ownership moves to `storage`; `inline.len` is reset below
+                //   so the elements are not dropped twice.
+                unsafe {
+                    core::ptr::copy_nonoverlapping(
+                        inline.data.as_ptr().cast(),
+                        storage.as_mut_ptr(),
+                        len,
+                    );
+                    storage.assume_init(len);
+                }
+                inline.len = 0;
+                storage
+            }
+            Repr::Heap(storage) => storage,
+        }
+    }
+
+    /// Returns an empty inline storage that will hold `numel` elements.
+    ///
+    /// Rejects what [`Storage`] rejects, so [`SmallStorage::into_storage`]
+    /// never has to.
+    fn empty_inline(numel: usize, alloc: A) -> Self {
+        assert!((size_of::<T>() != 0), "ZSTs are not supported.");
+        assert!(
+            (numel != 0),
+            "zero-sized buffers (numel=0) are not supported."
+        );
+        Self {
+            repr: Repr::Inline(
+                Inline {
+                    data: [const { MaybeUninit::uninit() }; N],
+                    len: 0,
+                },
+                alloc,
+            ),
+        }
+    }
+}
+
+impl<T: Clone, const N: usize, A: Allocator + Clone> SmallStorage<T, N, A> {
+    /// Creates a storage holding a clone of each element of `slice`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `slice` is empty, `T` is a zero-sized type, or `slice` does
+    /// not fit inline and the allocation fails.
+    pub fn from_slice(slice: &[T], alloc: A) -> Self {
+        if slice.len() > N {
+            return Self {
+                repr: Repr::Heap(Storage::from_slice(slice, alloc)),
+            };
+        }
+
+        let mut this = Self::empty_inline(slice.len(), alloc);
+        if let Repr::Inline(inline, _) = &mut this.repr {
+            for (slot, val) in inline.data.iter_mut().zip(slice) {
+                slot.write(val.clone());
+                inline.len += 1;
+            }
+        }
+        this
+    }
+
+    /// Creates a storage of `numel` elements, each cloned from `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `numel` is 0, `T` is a zero-sized type, or `numel` does not
+    /// fit inline and the allocation fails.
+    pub fn filled_with(numel: usize, value: T, alloc: A) -> Self {
+        if numel > N {
+            return Self {
+                repr: Repr::Heap(Storage::filled_with(numel, value, alloc)),
+            };
+        }
+
+        let mut this = Self::empty_inline(numel, alloc);
+        if let Repr::Inline(inline, _) = &mut this.repr {
+            for slot in &mut inline.data[..numel] {
+                slot.write(value.clone());
+                inline.len += 1;
+            }
+        }
+        this
+    }
+}
+
+impl<T: core::fmt::Debug, const N: usize, A: Allocator + Clone> core::fmt::Debug
+    for SmallStorage<T, N, A>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match &self.repr {
+            Repr::Inline(..) => f
+                .debug_struct("SmallStorage")
+                .field("inline", &N)
+                .field("data", &self.as_slice())
+                .finish(),
+            Repr::Heap(storage) => f.debug_tuple("SmallStorage").field(storage).finish(),
+        }
+    }
+}
+
+impl<T, const N: usize> Drop for Inline<T, N> {
+    fn drop(&mut self) {
+        // SAFETY:
+        // - elements `[0, len)` are initialized and dropped exactly once.
+        unsafe {
+            core::ptr::slice_from_raw_parts_mut(self.data.as_mut_ptr().cast::<T>(), self.len)
+                .drop_in_place();
+        }
+    }
+}
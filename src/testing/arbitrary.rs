@@ -0,0 +1,132 @@
+//! `proptest` strategies for [`Shape`]s and small tensor payloads.
+//!
+//! # Examples
+//!
+//! Inside a test suite these plug into `proptest! { ... }`; driving a
+//! [`TestRunner`](proptest::test_runner::TestRunner) directly works anywhere.
+//!
+//! ```
+//! use autodiff::shape::Shape;
+//! use autodiff::testing::arbitrary::{broadcastable_pair, matmul_pair, shape_and_data};
+//! use proptest::{prelude::*, test_runner::TestRunner};
+//!
+//! let mut runner = TestRunner::default();
+//! runner
+//!     .run(&any::<Shape>(), |shape| {
+//!         prop_assert!(shape.volume() > 0);
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! runner
+//!     .run(&broadcastable_pair(), |(a, b)| {
+//!         prop_assert!(a.broadcast(&b).is_ok());
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! runner
+//!     .run(&matmul_pair(), |(a, b)| {
+//!         prop_assert!(a.can_broadcast_matmul(&b).is_ok());
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! runner
+//!     .run(&shape_and_data::<f32>(), |(shape, data)| {
+//!         prop_assert_eq!(data.len(), shape.volume());
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! ```
+
+use core::ops::RangeInclusive;
+
+use alloc::vec::Vec;
+use proptest::{collection::vec, prelude::*};
+
+use crate::shape::Shape;
+
+/// Bounds used when generating an arbitrary [`Shape`].
+///
+/// Defaults keep volumes small (at most `8^4` elements) so that payloads
+/// generated alongside a shape stay cheap.
+#[derive(Debug, Clone)]
+pub struct ShapeParams {
+    /// Range of the number of dimensions.
+    pub ndims: RangeInclusive<usize>,
+    /// Range of each individual dimension size.
+    pub dim: RangeInclusive<usize>,
+}
+
+impl Default for ShapeParams {
+    fn default() -> Self {
+        Self {
+            ndims: 1..=4,
+            dim: 1..=8,
+        }
+    }
+}
+
+impl Arbitrary for Shape {
+    type Parameters = ShapeParams;
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(params: Self::Parameters) -> Self::Strategy {
+        vec(params.dim, params.ndims)
+            .prop_map(Shape::from)
+            .boxed()
+    }
+}
+
+/// Generates pairs of shapes that are broadcast-compatible.
+///
+/// Both shapes are derived from a common base by dropping leading dimensions
+/// (possibly all of them, yielding a scalar) and replacing some dimensions
+/// with `1`. See [`mod@crate::shape`] for the rules.
+pub fn broadcastable_pair() -> impl Strategy<Value = (Shape, Shape)> {
+    any::<Shape>()
+        .prop_flat_map(|base| {
+            let n = base.ndims();
+            let derived = move || (0..=n, vec(any::<bool>(), n));
+            (Just(base), derived(), derived())
+        })
+        .prop_map(|(base, (skip_a, ones_a), (skip_b, ones_b))| {
+            (
+                squash(&base, skip_a, &ones_a),
+                squash(&base, skip_b, &ones_b),
+            )
+        })
+}
+
+/// Generates pairs of shapes `[..., M, K]` and `[..., K, N]` whose batch
+/// dimensions are broadcast-compatible, i.e. valid inputs to
+/// [`Shape::can_broadcast_matmul`].
+pub fn matmul_pair() -> impl Strategy<Value = (Shape, Shape)> {
+    let dim = ShapeParams::default().dim;
+    (broadcastable_pair(), dim.clone(), dim.clone(), dim).prop_map(|((a, b), m, k, n)| {
+        let with = |batch: &Shape, rows: usize, cols: usize| {
+            let mut dims = batch.dims().to_vec();
+            dims.extend([rows, cols]);
+            Shape::from(dims)
+        };
+        (with(&a, m, k), with(&b, k, n))
+    })
+}
+
+/// Generates an arbitrary shape together with a flat, row-major payload of
+/// exactly [`Shape::volume`] elements.
+pub fn shape_and_data<T: Arbitrary>() -> impl Strategy<Value = (Shape, Vec<T>)> {
+    any::<Shape>().prop_flat_map(|shape| {
+        let numel = shape.volume();
+        (Just(shape), vec(any::<T>(), numel))
+    })
+}
+
+fn squash(base: &Shape, skip: usize, ones: &[bool]) -> Shape {
+    let dims: Vec<usize> = base
+        .dims()
+        .iter()
+        .zip(ones)
+        .skip(skip)
+        .map(|(&dim, &one)| if one { 1 } else { dim })
+        .collect();
+    Shape::from(dims)
+}
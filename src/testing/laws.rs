@@ -0,0 +1,117 @@
+//! Reusable assertions validating the shape semantics of custom ops.
+//!
+//! Each assertion takes the op under test as a shape function, i.e. a closure
+//! mapping input shapes to the output shape (or error) the op would produce,
+//! and panics with a descriptive message when the law does not hold.
+//!
+//! These pair naturally with the strategies in [`crate::testing::arbitrary`].
+//!
+//! # Examples
+//!
+//! ```
+//! use autodiff::shape::Shape;
+//! use autodiff::testing::{
+//!     arbitrary::{broadcastable_pair, matmul_pair},
+//!     laws,
+//! };
+//! use proptest::test_runner::TestRunner;
+//!
+//! let mut runner = TestRunner::default();
+//! runner
+//!     .run(&broadcastable_pair(), |(a, b)| {
+//!         laws::assert_broadcast_shape_law(Shape::broadcast, &a, &b);
+//!         laws::assert_broadcast_commutes(Shape::broadcast, &a, &b);
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! runner
+//!     .run(&(broadcastable_pair(), broadcastable_pair()), |((a, b), (c, _))| {
+//!         laws::assert_broadcast_associates(Shape::broadcast, &a, &b, &c);
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! runner
+//!     .run(&matmul_pair(), |(a, b)| {
+//!         laws::assert_matmul_shape_law(Shape::can_broadcast_matmul, &a, &b);
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! ```
+
+use crate::{error::TensorError, shape::Shape};
+
+/// Asserts that `op(a, b)` and `op(b, a)` agree: both succeed with the same
+/// shape, or both fail.
+///
+/// # Panics
+///
+/// Panics if the results differ.
+#[track_caller]
+pub fn assert_broadcast_commutes<F>(op: F, a: &Shape, b: &Shape)
+where
+    F: Fn(&Shape, &Shape) -> Result<Shape, TensorError>,
+{
+    let (ab, ba) = (op(a, b).ok(), op(b, a).ok());
+    assert_eq!(
+        ab, ba,
+        "broadcast does not commute for a: {a}, b: {b}\nop(a, b): {ab:?}\nop(b, a): {ba:?}"
+    );
+}
+
+/// Asserts that `op(op(a, b), c)` and `op(a, op(b, c))` agree: both succeed
+/// with the same shape, or both fail.
+///
+/// # Panics
+///
+/// Panics if only one side fails, or if both succeed with different shapes.
+#[track_caller]
+pub fn assert_broadcast_associates<F>(op: F, a: &Shape, b: &Shape, c: &Shape)
+where
+    F: Fn(&Shape, &Shape) -> Result<Shape, TensorError>,
+{
+    let left = op(a, b).and_then(|ab| op(&ab, c)).ok();
+    let right = op(b, c).and_then(|bc| op(a, &bc)).ok();
+    assert_eq!(
+        left, right,
+        "broadcast does not associate for a: {a}, b: {b}, c: {c}\n\
+         op(op(a, b), c): {left:?}\nop(a, op(b, c)): {right:?}"
+    );
+}
+
+/// Asserts that `op(a, b)` follows elementwise broadcasting semantics,
+/// using [`Shape::broadcast`] as the reference.
+///
+/// # Panics
+///
+/// Panics if `op` accepts inputs the reference rejects (or vice versa), or
+/// produces a different output shape.
+#[track_caller]
+pub fn assert_broadcast_shape_law<F>(op: F, a: &Shape, b: &Shape)
+where
+    F: Fn(&Shape, &Shape) -> Result<Shape, TensorError>,
+{
+    let (actual, expected) = (op(a, b).ok(), a.broadcast(b).ok());
+    assert_eq!(
+        actual, expected,
+        "broadcast shape law violated for a: {a}, b: {b}\nop: {actual:?}\nexpected: {expected:?}"
+    );
+}
+
+/// Asserts that `op(a, b)` follows batched matmul shape semantics,
+/// using [`Shape::can_broadcast_matmul`] as the reference.
+///
+/// # Panics
+///
+/// Panics if `op` accepts inputs the reference rejects (or vice versa), or
+/// produces a different output shape.
+#[track_caller]
+pub fn assert_matmul_shape_law<F>(op: F, a: &Shape, b: &Shape)
+where
+    F: Fn(&Shape, &Shape) -> Result<Shape, TensorError>,
+{
+    let (actual, expected) = (op(a, b).ok(), a.can_broadcast_matmul(b).ok());
+    assert_eq!(
+        actual, expected,
+        "matmul shape law violated for a: {a}, b: {b}\nop: {actual:?}\nexpected: {expected:?}"
+    );
+}
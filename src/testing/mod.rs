@@ -0,0 +1,6 @@
+//! Utilities for testing code built on top of this crate.
+//!
+//! Only available with the `testing` feature.
+
+pub mod arbitrary;
+pub mod laws;